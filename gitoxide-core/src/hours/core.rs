@@ -1,12 +1,13 @@
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, BTreeSet, HashMap},
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc,
     },
 };
 
-use gix::bstr::BStr;
+use gix::bstr::{BStr, ByteSlice};
+use serde::Serialize;
 
 use crate::hours::{
     util::{add_lines, remove_lines},
@@ -16,9 +17,89 @@ use crate::hours::{
 const MINUTES_PER_HOUR: f32 = 60.0;
 pub const HOURS_PER_WORKDAY: f32 = 8.0;
 
+/// A "year in review"-style breakdown of when an author's commits happened, in their local time
+/// at the time of each commit.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Activity {
+    /// Number of commits authored in each hour of the day, index 0 being midnight.
+    pub by_hour_of_day: [u32; 24],
+    /// Number of commits authored on each day of the week, index 0 being Sunday.
+    pub by_day_of_week: [u32; 7],
+    /// Number of commits per calendar month, oldest first, as `(year, month, count)` with `month`
+    /// in `1..=12`.
+    pub by_month: Vec<(i32, u8, u32)>,
+}
+
+impl Activity {
+    fn record(&mut self, seconds: gix::date::SecondsSinceUnixEpoch, utc_offset_in_seconds: i32) {
+        let local_seconds = seconds + i64::from(utc_offset_in_seconds);
+        let days_since_epoch = local_seconds.div_euclid(86_400);
+        let seconds_of_day = local_seconds.rem_euclid(86_400);
+
+        self.by_hour_of_day[(seconds_of_day / 3600) as usize] += 1;
+        // 1970-01-01 was a Thursday, i.e. weekday index 4 with Sunday as 0.
+        self.by_day_of_week[(days_since_epoch + 4).rem_euclid(7) as usize] += 1;
+
+        let (year, month, _day) = civil_from_days(days_since_epoch);
+        match self.by_month.last_mut() {
+            Some((y, m, count)) if *y == year && *m == month => *count += 1,
+            _ => self.by_month.push((year, month, 1)),
+        }
+    }
+
+    /// Add `other`'s counts into `self`, e.g. to combine the activity of several identities that
+    /// were unified into the same [`WorkByPerson`].
+    pub fn merge(&mut self, other: &Activity) {
+        for (a, b) in self.by_hour_of_day.iter_mut().zip(&other.by_hour_of_day) {
+            *a += b;
+        }
+        for (a, b) in self.by_day_of_week.iter_mut().zip(&other.by_day_of_week) {
+            *a += b;
+        }
+        for &(year, month, count) in &other.by_month {
+            match self.by_month.iter_mut().find(|(y, m, _)| *y == year && *m == month) {
+                Some((_, _, existing)) => *existing += count,
+                None => self.by_month.push((year, month, count)),
+            }
+        }
+        self.by_month.sort_unstable_by_key(|&(year, month, _)| (year, month));
+    }
+
+    /// Print a short "year in review"-style summary of this activity to `out`.
+    pub fn write_to(&self, mut out: impl std::io::Write) -> std::io::Result<()> {
+        const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+        if let Some((hour, count)) = self.by_hour_of_day.iter().enumerate().max_by_key(|(_, count)| **count) {
+            writeln!(out, "busiest hour: {hour:02}:00 ({count} commits)")?;
+        }
+        if let Some((day, count)) = self.by_day_of_week.iter().enumerate().max_by_key(|(_, count)| **count) {
+            writeln!(out, "busiest day: {} ({count} commits)", DAYS[day])?;
+        }
+        writeln!(out, "active across {} calendar month(s)", self.by_month.len())?;
+        Ok(())
+    }
+}
+
+/// Convert a day count since 1970-01-01 into a `(year, month, day)` triple, using the algorithm
+/// from Howard Hinnant's `chrono::civil_from_days` (a small, well-known closed-form inverse of
+/// the Gregorian calendar).
+fn civil_from_days(days_since_epoch: i64) -> (i32, u8, u8) {
+    let z = days_since_epoch + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = (z - era * 146_097) as u64;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let year = yoe as i64 + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = if month <= 2 { year + 1 } else { year };
+    (year as i32, month, day)
+}
+
 pub fn estimate_hours(
     commits: &[(u32, super::SignatureRef<'static>)],
     stats: &[(u32, FileStats, LineStats)],
+    track_activity: bool,
 ) -> WorkByEmail {
     assert!(!commits.is_empty());
     const MAX_COMMIT_DIFFERENCE_IN_MINUTES: f32 = 2.0 * MINUTES_PER_HOUR;
@@ -64,6 +145,15 @@ pub fn estimate_hours(
     } else {
         Default::default()
     };
+
+    let activity = track_activity.then(|| {
+        let mut activity = Activity::default();
+        for (_, signature) in commits.iter().rev() {
+            activity.record(signature.seconds(), signature.utc_offset_in_seconds());
+        }
+        activity
+    });
+
     WorkByEmail {
         name: author.name,
         email: author.email,
@@ -71,6 +161,123 @@ pub fn estimate_hours(
         num_commits: commits.len() as u32,
         files,
         lines,
+        activity,
+    }
+}
+
+/// The version of the on-disk format of [`DeltaStatsCache`]; a cache written with any other
+/// version is discarded as a whole.
+const DELTA_STATS_CACHE_FORMAT_VERSION: u32 = 2;
+
+const DELTA_STATS_CACHE_FILE_NAME: &str = "gitoxide_hours_delta_stats.cache";
+
+/// A persistent, on-disk cache of the [`FileStats`]/[`LineStats`] already computed for a given
+/// `(parent_tree_id, tree_id)` pair, so repeated `gix hours` runs over the same history only
+/// diff tree pairs they haven't seen before.
+///
+/// The cache is stored as a single file inside the repository's `.git` directory and is
+/// invalidated wholesale (i.e. treated as empty) if it was written in a different `line_stats`
+/// mode or for a different [`gix::hash::Kind`], or if [`DELTA_STATS_CACHE_FORMAT_VERSION`] has
+/// changed since it was written.
+#[derive(Default)]
+pub struct DeltaStatsCache {
+    line_stats: bool,
+    object_hash: gix::hash::Kind,
+    entries: HashMap<(gix::hash::ObjectId, gix::hash::ObjectId), (FileStats, LineStats)>,
+}
+
+impl DeltaStatsCache {
+    fn path(git_dir: &std::path::Path) -> std::path::PathBuf {
+        git_dir.join(DELTA_STATS_CACHE_FILE_NAME)
+    }
+
+    /// Load the cache for `git_dir`, starting out empty if none exists yet, or if the existing one
+    /// doesn't match `line_stats`/`object_hash` or was written by an incompatible format version.
+    pub fn load(git_dir: &std::path::Path, line_stats: bool, object_hash: gix::hash::Kind) -> Self {
+        std::fs::read(Self::path(git_dir))
+            .ok()
+            .and_then(|bytes| Self::decode(&bytes, line_stats, object_hash))
+            .unwrap_or(DeltaStatsCache {
+                line_stats,
+                object_hash,
+                entries: HashMap::new(),
+            })
+    }
+
+    fn decode(bytes: &[u8], line_stats: bool, object_hash: gix::hash::Kind) -> Option<Self> {
+        let (version, rest) = bytes.split_first_chunk::<4>()?;
+        if u32::from_le_bytes(*version) != DELTA_STATS_CACHE_FORMAT_VERSION {
+            return None;
+        }
+        let (&cached_line_stats, rest) = rest.split_first()?;
+        if (cached_line_stats != 0) != line_stats {
+            return None;
+        }
+        let (&cached_hash_kind, mut rest) = rest.split_first()?;
+        if cached_hash_kind != object_hash as u8 {
+            return None;
+        }
+        let hash_len = object_hash.len_in_bytes();
+
+        let mut entries = HashMap::new();
+        while !rest.is_empty() {
+            let (parent_tree_id, r) = rest.split_at_checked(hash_len)?;
+            let (tree_id, r) = r.split_at_checked(hash_len)?;
+            let (stats, r) = r.split_first_chunk::<20>()?;
+            let mut n = [0u8; 4];
+            let mut read_u32 = |offset: usize| {
+                n.copy_from_slice(&stats[offset..offset + 4]);
+                u32::from_le_bytes(n)
+            };
+            let files = FileStats {
+                added: read_u32(0) as usize,
+                removed: read_u32(4) as usize,
+                modified: read_u32(8) as usize,
+            };
+            let lines = LineStats {
+                added: read_u32(12) as usize,
+                removed: read_u32(16) as usize,
+            };
+            entries.insert(
+                (
+                    gix::hash::ObjectId::from_bytes_or_panic(parent_tree_id),
+                    gix::hash::ObjectId::from_bytes_or_panic(tree_id),
+                ),
+                (files, lines),
+            );
+            rest = r;
+        }
+        Some(DeltaStatsCache {
+            line_stats,
+            object_hash,
+            entries,
+        })
+    }
+
+    fn get(&self, parent_tree_id: &gix::hash::ObjectId, tree_id: &gix::hash::ObjectId) -> Option<(FileStats, LineStats)> {
+        self.entries.get(&(*parent_tree_id, *tree_id)).copied()
+    }
+
+    fn insert(&mut self, parent_tree_id: gix::hash::ObjectId, tree_id: gix::hash::ObjectId, files: FileStats, lines: LineStats) {
+        self.entries.insert((parent_tree_id, tree_id), (files, lines));
+    }
+
+    /// Persist this cache to `git_dir`, overwriting any cache already stored there.
+    pub fn store(&self, git_dir: &std::path::Path) -> std::io::Result<()> {
+        let mut buf = Vec::with_capacity(6 + self.entries.len() * 60);
+        buf.extend_from_slice(&DELTA_STATS_CACHE_FORMAT_VERSION.to_le_bytes());
+        buf.push(u8::from(self.line_stats));
+        buf.push(self.object_hash as u8);
+        for ((parent_tree_id, tree_id), (files, lines)) in &self.entries {
+            buf.extend_from_slice(parent_tree_id.as_slice());
+            buf.extend_from_slice(tree_id.as_slice());
+            buf.extend_from_slice(&(files.added as u32).to_le_bytes());
+            buf.extend_from_slice(&(files.removed as u32).to_le_bytes());
+            buf.extend_from_slice(&(files.modified as u32).to_le_bytes());
+            buf.extend_from_slice(&(lines.added as u32).to_le_bytes());
+            buf.extend_from_slice(&(lines.removed as u32).to_le_bytes());
+        }
+        std::fs::write(Self::path(git_dir), buf)
     }
 }
 
@@ -87,6 +294,7 @@ pub fn spawn_tree_delta_threads<'scope>(
     line_stats: bool,
     repo: gix::Repository,
     stat_counters: CommitChangeLineCounters,
+    stats_cache: std::sync::Arc<std::sync::Mutex<DeltaStatsCache>>,
 ) -> SpawnResultWithReturnChannelAndWorkers<'scope> {
     let (tx, rx) = crossbeam_channel::unbounded::<Vec<(CommitIdx, Option<gix::hash::ObjectId>, gix::hash::ObjectId)>>();
     let stat_workers = (0..threads)
@@ -96,6 +304,7 @@ pub fn spawn_tree_delta_threads<'scope>(
                 let mut repo = repo.clone();
                 repo.object_cache_size_if_unset((850 * 1024 * 1024) / threads);
                 let rx = rx.clone();
+                let stats_cache = stats_cache.clone();
                 move || -> Result<_, anyhow::Error> {
                     let mut out = Vec::new();
                     let (commits, changes, lines_count) = stats_counters;
@@ -126,6 +335,10 @@ pub fn spawn_tree_delta_threads<'scope>(
                                 Some(c) => c,
                                 None => continue,
                             };
+                            if let Some((files, lines)) = stats_cache.lock().expect("not poisoned").get(&from.id, &to.id) {
+                                out.push((commit_idx, files, lines));
+                                continue;
+                            }
                             from.changes()?
                                 .options(|opts| {
                                     opts.track_filename().track_rewrites(None);
@@ -182,8 +395,9 @@ pub fn spawn_tree_delta_threads<'scope>(
                                             }
                                         },
                                     }
-                                    Ok::<_, std::io::Error>(Default::default())
+                                    Ok::<_, std::io::Error>(std::ops::ControlFlow::Continue(()))
                                 })?;
+                            stats_cache.lock().expect("not poisoned").insert(from.id, to.id, files, lines);
                             out.push((commit_idx, files, lines));
                         }
                     }
@@ -195,11 +409,50 @@ pub fn spawn_tree_delta_threads<'scope>(
     (tx, stat_workers)
 }
 
-pub fn deduplicate_identities(persons: &[WorkByEmail]) -> Vec<WorkByPerson> {
+/// Merge `persons` that share an email or name into a single [`WorkByPerson`] each.
+///
+/// If `mailmap` is given, every author is first resolved through [`gix_mailmap::Snapshot::resolve()`]
+/// so that identities differing only by an aliased name or email (e.g. due to a machine-local email
+/// address) are recognized as the same person before the usual merging happens.
+pub fn deduplicate_identities(persons: &[WorkByEmail], mailmap: Option<&gix_mailmap::Snapshot>) -> Vec<WorkByPerson> {
     let mut email_to_index = HashMap::<&'static BStr, usize>::with_capacity(persons.len());
     let mut name_to_index = HashMap::<&'static BStr, usize>::with_capacity(persons.len());
     let mut out = Vec::<WorkByPerson>::with_capacity(persons.len());
+
+    let mut string_heap = BTreeSet::<&'static [u8]>::new();
+    let mut intern = |s: &BStr| -> &'static BStr {
+        match string_heap.get(s.as_bytes()) {
+            Some(existing) => existing.as_bstr(),
+            None => {
+                let owned: Box<[u8]> = s.to_vec().into_boxed_slice();
+                string_heap.insert(Box::leak(owned));
+                string_heap.get(s.as_bytes()).expect("just inserted").as_bstr()
+            }
+        }
+    };
+    let mut time_buf = gix_date::parse::TimeBuf::default();
+
     for person_by_email in persons {
+        let resolved_by_email;
+        let person_by_email = match mailmap {
+            Some(mailmap) => {
+                let signature = gix_actor::Signature {
+                    name: person_by_email.name.to_owned(),
+                    email: person_by_email.email.to_owned(),
+                    time: gix_date::Time::default(),
+                };
+                let resolved = mailmap.resolve(signature.to_ref(&mut time_buf));
+                resolved_by_email = WorkByEmail {
+                    name: intern(resolved.name.as_ref()),
+                    email: intern(resolved.email.as_ref()),
+                    activity: person_by_email.activity.clone(),
+                    ..*person_by_email
+                };
+                &resolved_by_email
+            }
+            None => person_by_email,
+        };
+
         match email_to_index.entry(person_by_email.email) {
             Entry::Occupied(email_entry) => {
                 out[*email_entry.get()].merge(person_by_email);
@@ -221,3 +474,325 @@ pub fn deduplicate_identities(persons: &[WorkByEmail]) -> Vec<WorkByPerson> {
     }
     out
 }
+
+/// A single person's contribution, in the shape written out by `--format json`.
+#[derive(Debug, Serialize)]
+pub struct PersonReport {
+    /// All names this person committed under, joined with `", "`.
+    pub name: String,
+    /// All email addresses this person committed under, joined with `", "`.
+    pub email: String,
+    /// The estimated amount of hours spent.
+    pub hours: f32,
+    /// The amount of commits attributed to this person.
+    pub num_commits: u32,
+    /// The amount of files added, removed and modified across all of this person's commits.
+    pub files: FileStats,
+    /// The amount of lines added and removed across all of this person's commits.
+    pub lines: LineStats,
+    /// When this person committed, if activity tracking was enabled.
+    pub activity: Option<Activity>,
+}
+
+/// Totals across every person in a [`Report`].
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    /// The total amount of hours estimated across all authors.
+    pub total_hours: f32,
+    /// The amount of distinct authors after identity deduplication.
+    pub total_authors: usize,
+    /// The total amount of commits across all authors.
+    pub total_commits: u32,
+    /// The total amount of files added, removed and modified.
+    pub total_files: FileStats,
+    /// The total amount of lines added and removed.
+    pub total_lines: LineStats,
+}
+
+/// The result of an hours estimation, holding per-person data and totals, as written out by
+/// `--format json`.
+///
+/// The human-readable output does not use this type: it prints each [`WorkByPerson`] directly and
+/// computes its own totals, whose commit, file and line counts also include commits by ignored
+/// bots, and whose author count is taken before identities are unified.
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    /// One entry per deduplicated person, sorted the same way they were passed in.
+    pub people: Vec<PersonReport>,
+    /// Totals across [`Report::people`].
+    pub summary: Summary,
+}
+
+impl Report {
+    /// Aggregate `people` (as produced by [`deduplicate_identities()`]) into a [`Report`].
+    ///
+    /// `show_pii` gates [`Report::people`] the same way it gates the human-readable per-author
+    /// paragraphs: when it's `false`, only the aggregate [`Report::summary`] is populated, and
+    /// no names or email addresses are serialized.
+    pub fn from_people(people: &[WorkByPerson], show_pii: bool) -> Self {
+        let mut summary = Summary {
+            total_authors: people.len(),
+            ..Summary::default()
+        };
+        let people = people
+            .iter()
+            .map(|person| {
+                summary.total_hours += person.hours;
+                summary.total_commits += person.num_commits;
+                summary.total_files.add(&person.files);
+                summary.total_lines.add(&person.lines);
+                PersonReport {
+                    name: person.name.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "),
+                    email: person.email.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "),
+                    hours: person.hours,
+                    num_commits: person.num_commits,
+                    files: person.files,
+                    lines: person.lines,
+                    activity: person.activity.clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+        Report {
+            people: if show_pii { people } else { Vec::new() },
+            summary,
+        }
+    }
+
+    /// Serialize this report as JSON, writing it to `out`.
+    pub fn write_json(&self, out: impl std::io::Write) -> serde_json::Result<()> {
+        serde_json::to_writer_pretty(out, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_from_days_handles_epoch_leap_years_and_negative_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1), "the Unix epoch itself");
+        assert_eq!(civil_from_days(-1), (1969, 12, 31), "a day before the epoch");
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29), "2024 is a leap year, so it has a Feb 29th");
+        assert_eq!(civil_from_days(19_416), (2023, 2, 28), "2023 is not a leap year, so Feb only has 28 days");
+    }
+
+    #[test]
+    fn deduplicate_identities_merges_by_shared_name_or_email_without_a_mailmap() {
+        let same_email = |name: &'static str| WorkByEmail {
+            name: name.as_bytes().as_bstr(),
+            email: b"jane@example.com".as_bstr(),
+            hours: 1.0,
+            num_commits: 1,
+            files: FileStats::default(),
+            lines: LineStats::default(),
+            activity: None,
+        };
+        let merged = deduplicate_identities(&[same_email("Jane"), same_email("J. Doe")], None);
+        assert_eq!(merged.len(), 1, "a shared email merges two differently-spelled names");
+        assert_eq!(merged[0].name, vec![b"Jane".as_bstr(), b"J. Doe".as_bstr()]);
+        assert_eq!(merged[0].num_commits, 2);
+
+        let same_name = |email: &'static str| WorkByEmail {
+            name: b"Jane".as_bstr(),
+            email: email.as_bytes().as_bstr(),
+            hours: 1.0,
+            num_commits: 1,
+            files: FileStats::default(),
+            lines: LineStats::default(),
+            activity: None,
+        };
+        let merged = deduplicate_identities(&[same_name("jane@work.example.com"), same_name("jane@home.example.com")], None);
+        assert_eq!(merged.len(), 1, "a shared name merges two different email addresses");
+        assert_eq!(
+            merged[0].email,
+            vec![b"jane@work.example.com".as_bstr(), b"jane@home.example.com".as_bstr()]
+        );
+
+        let unrelated = WorkByEmail {
+            name: b"Bob".as_bstr(),
+            email: b"bob@example.com".as_bstr(),
+            hours: 1.0,
+            num_commits: 1,
+            files: FileStats::default(),
+            lines: LineStats::default(),
+            activity: None,
+        };
+        let merged = deduplicate_identities(&[same_name("jane@work.example.com"), unrelated], None);
+        assert_eq!(merged.len(), 2, "identities sharing neither name nor email stay distinct");
+    }
+
+    #[test]
+    fn activity_record_buckets_by_local_time_and_merges() {
+        // 2024-01-01T00:16:40Z is a Monday; in UTC+9 that's already 09:16 local time.
+        let mut a = Activity::default();
+        a.record(1_704_068_200, 9 * 3600);
+        assert_eq!(a.by_hour_of_day[9], 1);
+        assert_eq!(a.by_day_of_week[1], 1, "Monday is index 1 with Sunday as 0");
+        assert_eq!(a.by_month, vec![(2024, 1, 1)]);
+
+        let mut b = Activity::default();
+        b.record(1_704_068_200, 9 * 3600);
+        a.merge(&b);
+        assert_eq!(a.by_hour_of_day[9], 2, "merging sums the hour-of-day histogram");
+        assert_eq!(a.by_day_of_week[1], 2);
+        assert_eq!(a.by_month, vec![(2024, 1, 2)], "merging sums same-month counters instead of duplicating them");
+    }
+
+    #[test]
+    fn report_from_people_omits_names_and_emails_unless_show_pii() {
+        let person = WorkByPerson {
+            name: vec![b"Jane Doe".as_bstr()],
+            email: vec![b"jane@example.com".as_bstr()],
+            hours: 4.0,
+            num_commits: 2,
+            files: FileStats::default(),
+            lines: LineStats::default(),
+            activity: None,
+        };
+
+        let report = Report::from_people(std::slice::from_ref(&person), false);
+        assert!(
+            report.people.is_empty(),
+            "without show_pii, no names or emails should be serialized, mirroring the human-readable path"
+        );
+        assert_eq!(report.summary.total_authors, 1, "the aggregate summary is unaffected by show_pii");
+        assert_eq!(report.summary.total_hours, 4.0);
+
+        let report = Report::from_people(std::slice::from_ref(&person), true);
+        assert_eq!(report.people.len(), 1);
+        assert_eq!(report.people[0].name, "Jane Doe");
+        assert_eq!(report.people[0].email, "jane@example.com");
+    }
+
+    #[test]
+    fn deduplicate_identities_merges_activity_keyed_by_resolved_email() {
+        // Both raw identities resolve to the same canonical email through the mailmap, so their
+        // activity histograms - recorded under the *raw* email - must still end up merged on the
+        // single, resolved person rather than being dropped.
+        let mailmap = gix_mailmap::Snapshot::new([
+            gix_mailmap::Entry::change_name_and_email_by_email("Jane Doe", "jane@example.com", "jane@laptop.(none)"),
+            gix_mailmap::Entry::change_name_and_email_by_email("Jane Doe", "jane@example.com", "jane@desktop.(none)"),
+        ]);
+
+        let mut laptop_activity = Activity::default();
+        laptop_activity.record(0, 0);
+        let mut desktop_activity = Activity::default();
+        desktop_activity.record(0, 0);
+
+        let persons = [
+            WorkByEmail {
+                name: b"Jane".as_bstr(),
+                email: b"jane@laptop.(none)".as_bstr(),
+                hours: 1.0,
+                num_commits: 1,
+                files: FileStats::default(),
+                lines: LineStats::default(),
+                activity: Some(laptop_activity),
+            },
+            WorkByEmail {
+                name: b"Jane".as_bstr(),
+                email: b"jane@desktop.(none)".as_bstr(),
+                hours: 2.0,
+                num_commits: 1,
+                files: FileStats::default(),
+                lines: LineStats::default(),
+                activity: Some(desktop_activity),
+            },
+        ];
+
+        let merged = deduplicate_identities(&persons, Some(&mailmap));
+        assert_eq!(merged.len(), 1, "both raw identities resolve to the same canonical email");
+        let activity = merged[0].activity.as_ref().expect("both inputs carried activity");
+        assert_eq!(
+            activity.by_hour_of_day[0], 2,
+            "activity recorded under either raw email must be summed on the resolved person"
+        );
+    }
+
+    #[test]
+    fn delta_stats_cache_round_trips_through_disk() {
+        let dir = std::env::temp_dir().join(format!(
+            "gitoxide-hours-delta-stats-cache-round-trip-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).expect("can create temp dir");
+
+        let parent_tree_id = gix::hash::ObjectId::from_bytes_or_panic(&[1u8; 20]);
+        let tree_id = gix::hash::ObjectId::from_bytes_or_panic(&[2u8; 20]);
+        let files = FileStats {
+            added: 3,
+            removed: 2,
+            modified: 1,
+        };
+        let lines = LineStats { added: 42, removed: 7 };
+
+        let mut cache = DeltaStatsCache {
+            line_stats: true,
+            object_hash: gix::hash::Kind::Sha1,
+            entries: HashMap::new(),
+        };
+        cache.insert(parent_tree_id, tree_id, files, lines);
+        cache.store(&dir).expect("storing the cache succeeds");
+
+        let loaded = DeltaStatsCache::load(&dir, true, gix::hash::Kind::Sha1);
+        assert_eq!(
+            loaded.get(&parent_tree_id, &tree_id),
+            Some((files, lines)),
+            "entries survive a store/load round-trip"
+        );
+
+        let loaded_with_mismatched_mode = DeltaStatsCache::load(&dir, false, gix::hash::Kind::Sha1);
+        assert!(
+            loaded_with_mismatched_mode.entries.is_empty(),
+            "a cache written with a different line_stats mode is treated as empty rather than misread"
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn delta_stats_cache_decode_rejects_truncated_and_mismatched_input() {
+        let mut valid = Vec::new();
+        valid.extend_from_slice(&DELTA_STATS_CACHE_FORMAT_VERSION.to_le_bytes());
+        valid.push(1); // line_stats: true
+        valid.push(gix::hash::Kind::Sha1 as u8);
+
+        assert!(
+            DeltaStatsCache::decode(&valid, true, gix::hash::Kind::Sha1).is_some(),
+            "the header alone, with no entries, decodes fine"
+        );
+        assert!(
+            DeltaStatsCache::decode(&valid[..valid.len() - 1], true, gix::hash::Kind::Sha1).is_none(),
+            "a truncated header is rejected"
+        );
+
+        let mut wrong_version = Vec::new();
+        wrong_version.extend_from_slice(&(DELTA_STATS_CACHE_FORMAT_VERSION + 1).to_le_bytes());
+        wrong_version.push(1);
+        wrong_version.push(gix::hash::Kind::Sha1 as u8);
+        assert!(
+            DeltaStatsCache::decode(&wrong_version, true, gix::hash::Kind::Sha1).is_none(),
+            "a cache from a different format version is discarded wholesale"
+        );
+
+        assert!(
+            DeltaStatsCache::decode(&valid, false, gix::hash::Kind::Sha1).is_none(),
+            "a cache written in a different line_stats mode is discarded"
+        );
+        let mut mismatched_hash_kind = Vec::new();
+        mismatched_hash_kind.extend_from_slice(&DELTA_STATS_CACHE_FORMAT_VERSION.to_le_bytes());
+        mismatched_hash_kind.push(1);
+        mismatched_hash_kind.push(gix::hash::Kind::Sha1 as u8 + 1);
+        assert!(
+            DeltaStatsCache::decode(&mismatched_hash_kind, true, gix::hash::Kind::Sha1).is_none(),
+            "a cache written for a different object hash kind is discarded"
+        );
+
+        let mut truncated_entry = valid.clone();
+        truncated_entry.extend_from_slice(&[9u8; 10]);
+        assert!(
+            DeltaStatsCache::decode(&truncated_entry, true, gix::hash::Kind::Sha1).is_none(),
+            "a partially written trailing entry is rejected rather than silently dropped"
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Parse `.mailmap` files and use them to map a commit's author/committer signature to the
+//! canonical identity of that person.
+//!
+//! See the [`gitmailmap` manual page](https://git-scm.com/docs/gitmailmap) for the format this
+//! crate implements.
+#![forbid(unsafe_code)]
+#![deny(missing_docs, rust_2018_idioms)]
+
+use std::{collections::HashMap, fmt};
+
+use bstr::{BStr, BString, ByteSlice};
+use gix_actor::{Signature, SignatureRef};
+
+mod parse;
+
+/// A single entry of a mailmap, mapping all or part of an old identity to a new, canonical one.
+///
+/// Note that despite the 'old' prefix, `old_name`/`old_email` aren't necessarily outdated, they
+/// are simply the identity as it appears in a commit, as opposed to the canonical `new_*` one
+/// that should be used instead.
+#[derive(Eq, PartialEq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[allow(missing_docs)]
+pub enum Entry {
+    ChangeName {
+        new_name: BString,
+        old_email: BString,
+    },
+    ChangeEmail {
+        new_email: BString,
+        old_email: BString,
+    },
+    ChangeNameAndEmail {
+        new_name: BString,
+        new_email: BString,
+        old_email: BString,
+    },
+    ChangeEmailByNameAndEmail {
+        new_email: BString,
+        old_name: BString,
+        old_email: BString,
+    },
+    ChangeNameAndEmailForNameAndEmail {
+        new_name: BString,
+        new_email: BString,
+        old_name: BString,
+        old_email: BString,
+    },
+}
+
+impl Entry {
+    /// Map any commit whose email is (case-insensitively) `old_email` to `new_name`, with the email
+    /// spelled as `old_email`. Corresponds to a mailmap line of the form `New Name <old-email>`.
+    pub fn change_name_by_email(new_name: impl Into<BString>, old_email: impl Into<BString>) -> Self {
+        Entry::ChangeName {
+            new_name: new_name.into(),
+            old_email: old_email.into(),
+        }
+    }
+
+    /// Map any commit whose email is (case-insensitively) `old_email` to `new_email`, keeping the
+    /// name as-is. Corresponds to a mailmap line of the form `<new-email> <old-email>`.
+    pub fn change_email_by_email(new_email: impl Into<BString>, old_email: impl Into<BString>) -> Self {
+        Entry::ChangeEmail {
+            new_email: new_email.into(),
+            old_email: old_email.into(),
+        }
+    }
+
+    /// Map any commit whose email is (case-insensitively) `old_email` to `new_name` and
+    /// `new_email`. Corresponds to a mailmap line of the form `New Name <new-email> <old-email>`.
+    pub fn change_name_and_email_by_email(
+        new_name: impl Into<BString>,
+        new_email: impl Into<BString>,
+        old_email: impl Into<BString>,
+    ) -> Self {
+        Entry::ChangeNameAndEmail {
+            new_name: new_name.into(),
+            new_email: new_email.into(),
+            old_email: old_email.into(),
+        }
+    }
+
+    /// Map any commit whose name and email are (case-insensitively) `old_name` and `old_email` to
+    /// `new_email`, keeping the name as-is. Corresponds to a mailmap line of the form
+    /// `<new-email> Old Name <old-email>`.
+    pub fn change_email_by_name_and_email(
+        new_email: impl Into<BString>,
+        old_name: impl Into<BString>,
+        old_email: impl Into<BString>,
+    ) -> Self {
+        Entry::ChangeEmailByNameAndEmail {
+            new_email: new_email.into(),
+            old_name: old_name.into(),
+            old_email: old_email.into(),
+        }
+    }
+
+    /// Map any commit whose name and email are (case-insensitively) `old_name` and `old_email` to
+    /// `new_name` and `new_email`. Corresponds to a mailmap line of the form
+    /// `New Name <new-email> Old Name <old-email>`.
+    pub fn change_name_and_email_by_name_and_email(
+        new_name: impl Into<BString>,
+        new_email: impl Into<BString>,
+        old_name: impl Into<BString>,
+        old_email: impl Into<BString>,
+    ) -> Self {
+        Entry::ChangeNameAndEmailForNameAndEmail {
+            new_name: new_name.into(),
+            new_email: new_email.into(),
+            old_name: old_name.into(),
+            old_email: old_email.into(),
+        }
+    }
+
+    /// The name that a matching signature should be changed to, if this entry specifies one.
+    pub fn new_name(&self) -> Option<&BStr> {
+        match self {
+            Entry::ChangeName { new_name, .. }
+            | Entry::ChangeNameAndEmail { new_name, .. }
+            | Entry::ChangeNameAndEmailForNameAndEmail { new_name, .. } => Some(new_name.as_ref()),
+            Entry::ChangeEmail { .. } | Entry::ChangeEmailByNameAndEmail { .. } => None,
+        }
+    }
+
+    /// The email that a matching signature should be changed to, if this entry specifies one.
+    pub fn new_email(&self) -> Option<&BStr> {
+        match self {
+            Entry::ChangeEmail { new_email, .. }
+            | Entry::ChangeNameAndEmail { new_email, .. }
+            | Entry::ChangeEmailByNameAndEmail { new_email, .. }
+            | Entry::ChangeNameAndEmailForNameAndEmail { new_email, .. } => Some(new_email.as_ref()),
+            Entry::ChangeName { .. } => None,
+        }
+    }
+
+    /// The original name this entry matches against, if it's part of the lookup key.
+    pub fn old_name(&self) -> Option<&BStr> {
+        match self {
+            Entry::ChangeEmailByNameAndEmail { old_name, .. }
+            | Entry::ChangeNameAndEmailForNameAndEmail { old_name, .. } => Some(old_name.as_ref()),
+            Entry::ChangeName { .. } | Entry::ChangeEmail { .. } | Entry::ChangeNameAndEmail { .. } => None,
+        }
+    }
+
+    /// The original email this entry matches against.
+    pub fn old_email(&self) -> &BStr {
+        match self {
+            Entry::ChangeName { old_email, .. }
+            | Entry::ChangeEmail { old_email, .. }
+            | Entry::ChangeNameAndEmail { old_email, .. }
+            | Entry::ChangeEmailByNameAndEmail { old_email, .. }
+            | Entry::ChangeNameAndEmailForNameAndEmail { old_email, .. } => old_email.as_ref(),
+        }
+    }
+
+    fn sort_key(&self) -> (BString, Option<BString>) {
+        (
+            self.old_email().to_lowercase().into(),
+            self.old_name().map(|name| name.to_lowercase().into()),
+        )
+    }
+}
+
+/// Render this entry as a single line of canonical `.mailmap` text, without a trailing newline.
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(new_name) = self.new_name() {
+            write!(f, "{new_name} ")?;
+        }
+        if let Some(new_email) = self.new_email() {
+            write!(f, "<{new_email}> ")?;
+        }
+        if let Some(old_name) = self.old_name() {
+            write!(f, "{old_name} ")?;
+        }
+        write!(f, "<{}>", self.old_email())
+    }
+}
+
+/// A parsed mailmap, ready to resolve author or committer signatures to their canonical identity.
+///
+/// Entries are kept sorted by their lowercased `old_email` (ties broken by `old_name`), which
+/// both makes [`Snapshot::entries()`] deterministic regardless of input order and allows
+/// [`Snapshot::try_resolve()`] to binary-search for the handful of entries that could possibly
+/// match a given email.
+#[derive(Default, Clone)]
+pub struct Snapshot {
+    entries: Vec<Entry>,
+    /// Maps the lowercased canonical (`new_email`, falling back to `old_email` for entries that
+    /// don't rewrite the email) to the indices of entries producing that canonical identity, for
+    /// use by [`Snapshot::aliases_of()`].
+    by_canonical_email: HashMap<BString, Vec<usize>>,
+}
+
+impl Snapshot {
+    /// Parse a mailmap from `bytes`, ignoring lines that are empty, comments, or don't match one
+    /// of the supported forms.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self::new(parse::lines(bytes))
+    }
+
+    /// Create a new snapshot from already-parsed `entries`.
+    ///
+    /// If multiple entries share the same `(old_name, old_email)` key, the last one wins.
+    pub fn new(entries: impl IntoIterator<Item = Entry>) -> Self {
+        let mut by_key = HashMap::<(BString, Option<BString>), usize>::new();
+        let mut out = Vec::new();
+        for entry in entries {
+            let key = entry.sort_key();
+            match by_key.get(&key) {
+                Some(&idx) => out[idx] = entry,
+                None => {
+                    by_key.insert(key, out.len());
+                    out.push(entry);
+                }
+            }
+        }
+        out.sort_by_key(Entry::sort_key);
+
+        let mut by_canonical_email = HashMap::<BString, Vec<usize>>::new();
+        for (idx, entry) in out.iter().enumerate() {
+            let canonical_email = entry.new_email().unwrap_or_else(|| entry.old_email()).to_lowercase();
+            by_canonical_email.entry(canonical_email.into()).or_default().push(idx);
+        }
+
+        Snapshot {
+            entries: out,
+            by_canonical_email,
+        }
+    }
+
+    /// Return all entries that make up this snapshot, sorted by their (lowercased) `old_email`.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Return every old identity in this snapshot that resolves to `canonical`, i.e. the reverse
+    /// of [`Snapshot::resolve()`].
+    ///
+    /// Only `canonical.email` is used to find matches, as that's the only part of an identity
+    /// that every [`Entry`] variant is guaranteed to fix to a single, canonical value - some
+    /// variants (like [`Entry::ChangeEmail`]) leave the name untouched and thus don't have a
+    /// single canonical name to match against.
+    pub fn aliases_of<'a>(&'a self, canonical: SignatureRef<'a>) -> impl Iterator<Item = SignatureRef<'a>> + 'a {
+        let canonical_email = canonical.email.to_lowercase();
+        self.by_canonical_email
+            .get(canonical_email.as_slice().as_bstr())
+            .into_iter()
+            .flatten()
+            .map(move |&idx| &self.entries[idx])
+            .map(move |entry| SignatureRef {
+                name: entry.old_name().unwrap_or(canonical.name),
+                email: entry.old_email(),
+                time: canonical.time,
+            })
+    }
+
+    /// Resolve `signature` to its canonical identity, or return `None` if no entry matches it.
+    pub fn try_resolve(&self, signature: SignatureRef<'_>) -> Option<Signature> {
+        let email_lower: BString = signature.email.to_lowercase().into();
+        let start = self
+            .entries
+            .partition_point(|entry| entry.old_email().to_lowercase().as_slice() < email_lower.as_slice());
+
+        let mut fallback = None;
+        for entry in self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.old_email().eq_ignore_ascii_case(email_lower.as_slice()))
+        {
+            match entry.old_name() {
+                Some(old_name) if old_name.eq_ignore_ascii_case(signature.name) => {
+                    return Some(self.apply(entry, signature))
+                }
+                Some(_) => continue,
+                None => fallback = Some(entry),
+            }
+        }
+        fallback.map(|entry| self.apply(entry, signature))
+    }
+
+    /// Resolve `signature`, falling back to a copy of `signature` itself if no entry matches it.
+    pub fn resolve(&self, signature: SignatureRef<'_>) -> Signature {
+        self.try_resolve(signature).unwrap_or_else(|| signature.into())
+    }
+
+    fn apply(&self, entry: &Entry, signature: SignatureRef<'_>) -> Signature {
+        Signature {
+            name: entry.new_name().map(ToOwned::to_owned).unwrap_or_else(|| signature.name.to_owned()),
+            email: entry
+                .new_email()
+                .map(ToOwned::to_owned)
+                .unwrap_or_else(|| entry.old_email().to_owned()),
+            time: signature.time().unwrap_or_default(),
+        }
+    }
+}
+
+/// Render this snapshot as canonical `.mailmap` text, one entry per line.
+///
+/// `Snapshot::from_bytes(snapshot.to_string().as_bytes())` reproduces an equivalent snapshot,
+/// i.e. `to_bytes`/[`Display`](fmt::Display) is a fixed point of [`Snapshot::from_bytes()`].
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{entry}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Snapshot {
+    /// Render this snapshot as canonical `.mailmap` text, one entry per line.
+    ///
+    /// See the [`Display`](fmt::Display) implementation for details.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
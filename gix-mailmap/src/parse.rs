@@ -0,0 +1,60 @@
+use bstr::{BStr, ByteSlice};
+
+use crate::Entry;
+
+/// Parse the given mailmap `bytes` line by line, yielding one [`Entry`] per valid line.
+///
+/// Lines that are empty, start with `#` (a comment), or don't match any of the supported
+/// mailmap forms are silently ignored, matching `git`'s own leniency towards malformed
+/// `.mailmap` files.
+pub(crate) fn lines(bytes: &[u8]) -> impl Iterator<Item = Entry> + '_ {
+    bytes.lines().filter_map(|line| parse_line(line.trim()))
+}
+
+fn parse_line(line: &[u8]) -> Option<Entry> {
+    let line = line.as_bstr();
+    if line.is_empty() || line.starts_with(b"#") {
+        return None;
+    }
+
+    let (before_first, first_email, after_first) = split_at_email(line)?;
+    let first_name = trim_name(before_first);
+
+    if after_first.trim().is_empty() {
+        return Some(match first_name {
+            Some(name) => Entry::change_name_by_email(name, first_email),
+            None => return None,
+        });
+    }
+
+    let (before_second, second_email, after_second) = split_at_email(after_first)?;
+    if !after_second.trim().is_empty() {
+        return None;
+    }
+    let second_name = trim_name(before_second);
+
+    Some(match (first_name, second_name) {
+        (Some(new_name), Some(old_name)) => {
+            Entry::change_name_and_email_by_name_and_email(new_name, first_email, old_name, second_email)
+        }
+        (Some(new_name), None) => Entry::change_name_and_email_by_email(new_name, first_email, second_email),
+        (None, Some(old_name)) => Entry::change_email_by_name_and_email(first_email, old_name, second_email),
+        (None, None) => Entry::change_email_by_email(first_email, second_email),
+    })
+}
+
+/// Split `line` at the first `<...>` group, returning `(before, inside, after)`.
+fn split_at_email(line: &BStr) -> Option<(&BStr, &BStr, &BStr)> {
+    let open = line.find_byte(b'<')?;
+    let close = open + line[open..].find_byte(b'>')?;
+    Some((
+        line[..open].as_bstr(),
+        line[open + 1..close].as_bstr(),
+        line[close + 1..].as_bstr(),
+    ))
+}
+
+fn trim_name(name: &BStr) -> Option<&BStr> {
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.as_bstr())
+}
@@ -1,3 +1,4 @@
+use bstr::ByteSlice;
 use gix_date::parse::TimeBuf;
 use gix_mailmap::{Entry, Snapshot};
 use gix_testtools::fixture_bytes;
@@ -161,6 +162,67 @@ fn overwrite_entries() {
     );
 }
 
+#[test]
+fn to_bytes_is_a_fixed_point_of_from_bytes() {
+    let entries = vec![
+        Entry::change_name_by_email("new-name", "old-email"),
+        Entry::change_email_by_email("new-email", "other-old-email"),
+        Entry::change_name_and_email_by_email("New Name", "new-email", "yet-another-old-email"),
+        Entry::change_email_by_name_and_email("new-email", "Old Name", "old-email-2"),
+        Entry::change_name_and_email_by_name_and_email("New Name", "new-email", "Old Name", "old-email-3"),
+    ];
+    let snapshot = Snapshot::new(entries);
+
+    let bytes = snapshot.to_bytes();
+    let round_tripped = Snapshot::from_bytes(&bytes);
+    assert_eq!(
+        round_tripped.entries(),
+        snapshot.entries(),
+        "serializing a snapshot and parsing it back yields the same entries"
+    );
+}
+
+#[test]
+fn aliases_of_finds_every_old_identity_for_a_canonical_one() {
+    let entries = vec![
+        Entry::change_name_and_email_by_email("Jane Doe", "jane@example.com", "jane@laptop.(none)"),
+        Entry::change_name_and_email_by_email("Jane Doe", "jane@example.com", "jane@desktop.(none)"),
+        Entry::change_name_by_email("Joe R. Developer", "joe@example.com"),
+    ];
+    let snapshot = Snapshot::new(entries);
+    let mut buf = TimeBuf::default();
+
+    let aliases: Vec<_> = snapshot
+        .aliases_of(signature("Jane Doe", "jane@example.com").to_ref(&mut buf))
+        .map(|sig| (sig.name.to_owned(), sig.email.to_owned()))
+        .collect();
+    assert_eq!(
+        aliases,
+        vec![
+            (b"Jane Doe".as_bstr().to_owned(), b"jane@desktop.(none)".as_bstr().to_owned()),
+            (b"Jane Doe".as_bstr().to_owned(), b"jane@laptop.(none)".as_bstr().to_owned()),
+        ],
+        "both machine-local emails that collapse into the canonical identity are found, \
+         sorted the same way `entries()` is"
+    );
+
+    assert_eq!(
+        snapshot
+            .aliases_of(signature("Joe R. Developer", "joe@example.com").to_ref(&mut buf))
+            .count(),
+        1,
+        "a `change_name_by_email` entry is its own alias, since it doesn't rewrite the email"
+    );
+
+    assert_eq!(
+        snapshot
+            .aliases_of(signature("nobody", "unknown@example.com").to_ref(&mut buf))
+            .count(),
+        0,
+        "canonical identities that aren't the target of any mapping have no aliases"
+    );
+}
+
 fn signature(name: &str, email: &str) -> gix_actor::Signature {
     gix_actor::Signature {
         name: name.into(),